@@ -0,0 +1,77 @@
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Error, PgPool};
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Centralizes the environment variables `main` needs to stand up the
+/// service, so there's a single place to look when deploying against a
+/// managed Postgres instance or tuning the pool.
+pub struct Config {
+    pub database_url: String,
+    pub bind_address: String,
+    pub max_connections: u32,
+    pub acquire_timeout: Duration,
+    pub log_statements: bool,
+}
+
+impl Config {
+    pub fn init() -> Self {
+        let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| {
+            let postgres_user =
+                env::var("POSTGRES_USER").unwrap_or_else(|_| "postgres".to_string());
+            let postgres_password =
+                env::var("POSTGRES_PASSWORD").unwrap_or_else(|_| "postgres".to_string());
+            let postgres_host =
+                env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".to_string());
+            let postgres_port = env::var("POSTGRES_PORT").unwrap_or_else(|_| "5432".to_string());
+            let postgres_db = env::var("POSTGRES_DB").unwrap_or_else(|_| "postgres".to_string());
+
+            format!(
+                "postgres://{}:{}@{}:{}/{}",
+                postgres_user, postgres_password, postgres_host, postgres_port, postgres_db
+            )
+        });
+
+        let bind_address =
+            env::var("BIND_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+
+        let max_connections = env::var("DATABASE_MAX_CONNECTIONS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(10);
+
+        let acquire_timeout = env::var("DATABASE_ACQUIRE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| Duration::from_secs(30));
+
+        let log_statements = env::var("DATABASE_LOG_STATEMENTS")
+            .ok()
+            .map(|value| value != "false" && value != "0")
+            .unwrap_or(true);
+
+        Self {
+            database_url,
+            bind_address,
+            max_connections,
+            acquire_timeout,
+            log_statements,
+        }
+    }
+
+    pub async fn create_pool(&self) -> Result<PgPool, Error> {
+        let mut connect_options = PgConnectOptions::from_str(&self.database_url)?;
+
+        if !self.log_statements {
+            connect_options = connect_options.disable_statement_logging();
+        }
+
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .connect_with(connect_options)
+            .await
+    }
+}
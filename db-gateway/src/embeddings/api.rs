@@ -1,8 +1,15 @@
+use axum::{
+    extract::{Extension, Json},
+    http::StatusCode,
+    response::IntoResponse,
+};
 use reqwest;
+use serde::Deserialize;
 use serde_json;
 use std::env;
 use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 use tracing;
 
 #[derive(Debug)]
@@ -26,7 +33,7 @@ impl fmt::Display for EmbeddingError {
 
 impl Error for EmbeddingError {}
 
-pub async fn get_embedding(text: &str) -> Result<Vec<f64>, Box<dyn Error>> {
+pub async fn get_embedding(text: &str) -> Result<Vec<f32>, Box<dyn Error>> {
     let api_key =
         env::var("OPENAI_API_KEY").map_err(|_| EmbeddingError::new("OPENAI_API_KEY not set"))?;
 
@@ -53,15 +60,48 @@ pub async fn get_embedding(text: &str) -> Result<Vec<f64>, Box<dyn Error>> {
         EmbeddingError::new("Failed to parse response as JSON")
     })?;
 
-    let embedding: Vec<f64> = response_json["data"][0]["embedding"]
+    let embedding: Vec<f32> = response_json["data"][0]["embedding"]
         .as_array()
         .ok_or_else(|| {
             tracing::error!("Invalid response format");
             EmbeddingError::new("Invalid response format")
         })?
         .iter()
-        .map(|v| v.as_f64().unwrap_or(0.0))
+        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
         .collect();
 
     Ok(embedding)
 }
+
+#[derive(Deserialize)]
+pub struct SearchPayload {
+    query: Option<String>,
+    limit: Option<i64>,
+}
+
+pub async fn search(
+    Extension(pool): Extension<Arc<sqlx::PgPool>>,
+    Json(payload): Json<SearchPayload>,
+) -> impl IntoResponse {
+    let query = match payload.query {
+        Some(query) => query,
+        None => return (StatusCode::BAD_REQUEST, Json(Vec::new())),
+    };
+    let limit = payload.limit.unwrap_or(10);
+
+    let embedding = match get_embedding(&query).await {
+        Ok(embedding) => embedding,
+        Err(e) => {
+            tracing::error!("Failed to get embedding for search query: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::new()));
+        }
+    };
+
+    match crate::embeddings::db::search_embeddings(pool.as_ref(), &embedding, limit).await {
+        Ok(results) => (StatusCode::OK, Json(results)),
+        Err(e) => {
+            tracing::error!("Failed to search embeddings: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::new()))
+        }
+    }
+}
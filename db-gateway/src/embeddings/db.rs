@@ -1,69 +1,30 @@
+use serde::Serialize;
 use serde_json;
 use sqlx::PgPool;
 use tracing;
 
-pub async fn create_embeddings_table(pool: &PgPool) -> Result<(), sqlx::Error> {
-    match sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS embeddings (
-          id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
-          embedding vector,
-          content JSONB,
-          created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-          updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            tracing::error!("Failed to create table: {}", e);
-        }
-    }
-
-    match sqlx::query(
-        r#"
-        DROP TRIGGER IF EXISTS updated_at ON embeddings;
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            tracing::error!("Failed to drop trigger: {}", e);
+/// pgvector's text input format is a bracketed, comma-separated list of
+/// floats (e.g. `[0.1,0.2,0.3]`), bound as text and cast with `::vector`.
+fn to_pgvector_literal(embedding: &[f32]) -> String {
+    let mut literal = String::with_capacity(embedding.len() * 8 + 2);
+    literal.push('[');
+    for (i, value) in embedding.iter().enumerate() {
+        if i > 0 {
+            literal.push(',');
         }
+        literal.push_str(&value.to_string());
     }
-
-    match sqlx::query(
-        r#"
-        CREATE TRIGGER updated_at
-          BEFORE UPDATE ON embeddings
-          FOR EACH ROW
-          EXECUTE FUNCTION updated_at();
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            tracing::error!("Failed to create trigger: {}", e);
-        }
-    }
-
-    Ok(())
+    literal.push(']');
+    literal
 }
 
 pub async fn insert_embedding(
     pool: &PgPool,
-    embedding: &Vec<f64>,
+    embedding: &[f32],
     content: serde_json::Value,
 ) -> Result<(), sqlx::Error> {
-    match sqlx::query(r#"INSERT INTO embeddings (embedding, content) VALUES ($1, $2)"#)
-        .bind(embedding)
+    match sqlx::query(r#"INSERT INTO embeddings (embedding, content) VALUES ($1::vector, $2)"#)
+        .bind(to_pgvector_literal(embedding))
         .bind(content)
         .execute(pool)
         .await
@@ -78,3 +39,48 @@ pub async fn insert_embedding(
 
     Ok(())
 }
+
+#[derive(sqlx::FromRow, Serialize)]
+pub struct SearchResult {
+    pub content: Option<serde_json::Value>,
+    pub similarity: f64,
+}
+
+pub async fn search_embeddings(
+    pool: &PgPool,
+    embedding: &[f32],
+    limit: i64,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    sqlx::query_as::<_, SearchResult>(
+        r#"
+        SELECT content, 1 - (embedding <=> $1::vector) AS similarity
+        FROM embeddings
+        ORDER BY embedding <=> $1::vector
+        LIMIT $2
+        "#,
+    )
+    .bind(to_pgvector_literal(embedding))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_vector_produces_empty_brackets() {
+        assert_eq!(to_pgvector_literal(&[]), "[]");
+    }
+
+    #[test]
+    fn formats_values_as_comma_separated_list() {
+        assert_eq!(to_pgvector_literal(&[0.1, 0.2, 0.3]), "[0.1,0.2,0.3]");
+    }
+
+    #[test]
+    fn formats_a_single_value_without_trailing_comma() {
+        assert_eq!(to_pgvector_literal(&[1.5]), "[1.5]");
+    }
+}
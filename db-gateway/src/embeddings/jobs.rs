@@ -1,10 +1,51 @@
 use crate::embeddings::api::get_embedding;
+use crate::embeddings::db::insert_embedding;
 use sqlx::PgPool;
 use std::sync::Arc;
+use tracing::{error, info};
 
 pub async fn embed(pool: Arc<PgPool>) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
     loop {
-        interval.tick().await;
+        match crate::queue::dequeue(&pool, "embed").await {
+            Ok(Some(job)) => {
+                info!("Embedding job {}", job.id);
+
+                let heartbeat = crate::queue::spawn_heartbeat(
+                    pool.clone(),
+                    job.id,
+                    std::time::Duration::from_secs(10),
+                );
+
+                let text = job.job.get("text").and_then(|v| v.as_str()).unwrap_or("");
+                let result = get_embedding(text).await;
+                heartbeat.abort();
+
+                match result {
+                    Ok(embedding) => {
+                        if let Err(e) = insert_embedding(&pool, &embedding, job.job.clone()).await
+                        {
+                            error!("Failed to insert embedding for job {}: {}", job.id, e);
+                        }
+
+                        if let Err(e) = crate::queue::complete(&pool, job.id).await {
+                            error!("Failed to complete job {}: {}", job.id, e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to get embedding for job {}: {}", job.id, e);
+                        if let Err(e) = crate::queue::fail(&pool, job.id, &e.to_string()).await {
+                            error!("Failed to mark job {} as failed: {}", job.id, e);
+                        }
+                    }
+                }
+            }
+            Ok(None) => {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            Err(e) => {
+                error!("Failed to dequeue embed job: {}", e);
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+        }
     }
 }
@@ -3,108 +3,168 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use sqlx::{Error, PgPool};
+use sqlx::PgPool;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 use uuid::Uuid;
 
-pub async fn create_table(pool: &PgPool) -> Result<(), Error> {
-    // CREATE EXTENSION and TABLE commands are separated.
-    match sqlx::query(
-        r#"
-        CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create extension: {}", e);
-        }
-    }
+#[derive(sqlx::FromRow)]
+struct SnapshotContent {
+    content: Option<serde_json::Value>,
+}
 
-    match sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS texts (
-          id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
-          content JSONB,
-          snapshot_id uuid,
-          FOREIGN KEY (snapshot_id) REFERENCES snapshots(id),
-          created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-          updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create table: {}", e);
-        }
-    }
+/// Strips script/style content (by only ever looking inside heading and
+/// paragraph elements), collapses runs of whitespace within each block, and
+/// keeps block boundaries as blank lines so headings/paragraphs stay distinct.
+fn extract_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("h1, h2, h3, h4, h5, h6, p").unwrap();
 
-    // Each part of function and trigger creation is a separate command.
-    match sqlx::query(
-        r#"
-        CREATE OR REPLACE FUNCTION update_updated_at_column()
-          RETURNS TRIGGER AS $$
-          BEGIN
-            NEW.updated_at = NOW();
-            RETURN NEW;
-          END;
-          $$ language 'plpgsql';
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create function: {}", e);
-        }
-    }
+    document
+        .select(&selector)
+        .map(|element| element.text().collect::<Vec<_>>().join(" "))
+        .map(|block| block.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|block| !block.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+async fn parse_snapshot(
+    pool: &PgPool,
+    job: &serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let snapshot_id: Uuid = job
+        .get("snapshot_id")
+        .and_then(|v| v.as_str())
+        .ok_or("job is missing snapshot_id")?
+        .parse()?;
 
-    match sqlx::query(
+    let snapshot = sqlx::query_as::<_, SnapshotContent>(
         r#"
-        DROP TRIGGER IF EXISTS update_updated_at ON texts;
+        SELECT content FROM snapshots WHERE id = $1
         "#,
     )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to drop trigger: {}", e);
-        }
-    }
+    .bind(snapshot_id)
+    .fetch_one(pool)
+    .await?;
 
-    match sqlx::query(
+    let html = snapshot
+        .content
+        .as_ref()
+        .and_then(|content| content.get("html"))
+        .and_then(|value| value.as_str())
+        .ok_or("snapshot has no html content")?;
+
+    let text = extract_text(html);
+
+    // Re-delivery (reaper requeue, or a duplicate fire-and-forget enqueue)
+    // can hand the same snapshot to this worker more than once; the unique
+    // index on `snapshot_id` makes the insert a no-op the second time, so we
+    // only enqueue the embed job when we actually inserted a new row.
+    let result = sqlx::query(
         r#"
-        CREATE TRIGGER update_updated_at
-          BEFORE UPDATE ON texts
-          FOR EACH ROW
-          EXECUTE FUNCTION update_updated_at_column();
+        INSERT INTO texts (content, snapshot_id)
+        VALUES ($1, $2)
+        ON CONFLICT (snapshot_id) WHERE snapshot_id IS NOT NULL DO NOTHING
         "#,
     )
+    .bind(serde_json::json!({ "text": &text }))
+    .bind(snapshot_id)
     .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create trigger: {}", e);
-        }
+    .await?;
+
+    if result.rows_affected() == 0 {
+        debug!("Snapshot {} already parsed, skipping embed enqueue", snapshot_id);
+        return Ok(());
     }
 
+    crate::queue::enqueue(pool, "embed", serde_json::json!({ "text": text })).await?;
+
     Ok(())
 }
 
 pub async fn html_parser(pool: Arc<PgPool>) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
     loop {
-        interval.tick().await;
-        info!("Parsing HTML")
+        match crate::queue::dequeue(&pool, "parse").await {
+            Ok(Some(job)) => {
+                info!("Parsing HTML for job {}", job.id);
+
+                let heartbeat = crate::queue::spawn_heartbeat(
+                    pool.clone(),
+                    job.id,
+                    std::time::Duration::from_secs(10),
+                );
+
+                let result = parse_snapshot(&pool, &job.job).await;
+                heartbeat.abort();
+
+                match result {
+                    Ok(_) => {
+                        if let Err(e) = crate::queue::complete(&pool, job.id).await {
+                            error!("Failed to complete job {}: {}", job.id, e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to parse snapshot for job {}: {}", job.id, e);
+                        if let Err(e) = crate::queue::fail(&pool, job.id, &e.to_string()).await {
+                            error!("Failed to mark job {} as failed: {}", job.id, e);
+                        }
+                    }
+                }
+            }
+            Ok(None) => {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            Err(e) => {
+                error!("Failed to dequeue parse job: {}", e);
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_script_and_style_content() {
+        let html = r#"
+            <html>
+              <head><style>p { color: red; }</style></head>
+              <body>
+                <script>alert("hi")</script>
+                <p>Hello world</p>
+              </body>
+            </html>
+        "#;
+
+        assert_eq!(extract_text(html), "Hello world");
+    }
+
+    #[test]
+    fn collapses_whitespace_within_a_block() {
+        let html = "<p>Hello\n   world,\t  how are   you</p>";
+
+        assert_eq!(extract_text(html), "Hello world, how are you");
+    }
+
+    #[test]
+    fn keeps_blocks_separated_by_blank_lines() {
+        let html = "<h1>Title</h1><p>First paragraph.</p><p>Second paragraph.</p>";
+
+        assert_eq!(
+            extract_text(html),
+            "Title\n\nFirst paragraph.\n\nSecond paragraph."
+        );
+    }
+
+    #[test]
+    fn skips_empty_blocks() {
+        let html = "<p></p><p>   </p><p>Real content</p>";
+
+        assert_eq!(extract_text(html), "Real content");
     }
 }
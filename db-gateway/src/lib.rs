@@ -1,12 +1,75 @@
+pub mod config;
+pub mod embeddings;
 pub mod html_parser;
+pub mod migrations;
+pub mod queue;
 pub mod snapshots;
 pub mod urls;
 
-use sqlx::{Error, PgPool, Row};
-use tracing::debug;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use sqlx::{PgPool, Row};
+use thiserror::Error as ThisError;
+use tracing::{debug, error};
 use url::{ParseError, Url};
 
-pub async fn print_table_schema(pool: &PgPool, table_name: &str) -> Result<(), Error> {
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Sqlx(sqlx::Error),
+
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+
+    #[error("url already exists")]
+    UrlExists,
+
+    #[error("not found")]
+    NotFound,
+
+    #[error("bad request: {0}")]
+    BadRequest(String),
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.is_unique_violation()
+                && db_err.table() == Some("urls")
+                && db_err.constraint() == Some("urls_url_key")
+            {
+                return Error::UrlExists;
+            }
+        }
+
+        Error::Sqlx(err)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (status, message) = match &self {
+            Error::Sqlx(e) => {
+                error!("database error: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error".to_string())
+            }
+            Error::Json(e) => (StatusCode::BAD_REQUEST, format!("invalid json: {}", e)),
+            Error::InvalidUrl(url) => (StatusCode::BAD_REQUEST, format!("invalid url: {}", url)),
+            Error::UrlExists => (StatusCode::CONFLICT, "url already exists".to_string()),
+            Error::NotFound => (StatusCode::NOT_FOUND, "not found".to_string()),
+            Error::BadRequest(message) => (StatusCode::BAD_REQUEST, message.clone()),
+        };
+
+        (status, message).into_response()
+    }
+}
+
+pub async fn print_table_schema(pool: &PgPool, table_name: &str) -> Result<(), sqlx::Error> {
     let rows = sqlx::query("SELECT column_name, data_type, is_nullable FROM information_schema.columns WHERE table_name = $1")
         .bind(table_name)
         .fetch_all(pool)
@@ -3,10 +3,8 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use sqlx::PgPool;
-use std::env;
+use db_gateway::config::Config;
 use std::sync::Arc;
-use tracing::info;
 use tracing_subscriber;
 
 #[tokio::main]
@@ -15,37 +13,30 @@ async fn main() {
 
     // Postgres Connection Pool
 
-    let postgres_user = env::var("POSTGRES_USER").unwrap_or_else(|_| "postgres".to_string());
-    let postgres_password =
-        env::var("POSTGRES_PASSWORD").unwrap_or_else(|_| "postgres".to_string());
-    let postgres_host = env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".to_string());
-    let postgres_db = env::var("POSTGRES_DB").unwrap_or_else(|_| "postgres".to_string());
-    let postgres_url = format!(
-        "postgres://{}:{}@{}:5432/{}",
-        postgres_user, postgres_password, postgres_host, postgres_db
-    );
+    let config = Config::init();
 
     let pool = Arc::new(
-        PgPool::connect(&postgres_url)
+        config
+            .create_pool()
             .await
             .expect("Failed to create PgPool"),
     );
 
-    // Create tables if they don't exist
+    // Apply pending schema migrations
 
-    db_gateway::snapshots::create_snapshots_table(&pool.clone())
-        .await
-        .unwrap();
-    db_gateway::urls::create_urls_table(&pool.clone())
+    db_gateway::migrations::run(&pool)
         .await
-        .unwrap();
-    db_gateway::html_parser::create_table(&pool.clone())
-        .await
-        .unwrap();
+        .expect("Failed to run migrations");
 
     // Start Jobs
 
     tokio::spawn(db_gateway::html_parser::html_parser(pool.clone()));
+    tokio::spawn(db_gateway::embeddings::jobs::embed(pool.clone()));
+    tokio::spawn(db_gateway::queue::reaper(
+        pool.clone(),
+        std::time::Duration::from_secs(30),
+        std::time::Duration::from_secs(300),
+    ));
 
     // Run Server
 
@@ -60,9 +51,15 @@ async fn main() {
             "/api/snapshots",
             post(db_gateway::snapshots::insert_snapshot),
         )
+        .route("/api/search", post(db_gateway::embeddings::api::search))
         .layer(Extension(pool.clone()));
 
-    axum::Server::bind(&"0.0.0.0:8080".parse().unwrap())
+    let addr = config
+        .bind_address
+        .parse()
+        .expect("Invalid BIND_ADDRESS");
+
+    axum::Server::bind(&addr)
         .serve(app.into_make_service())
         .await
         .unwrap();
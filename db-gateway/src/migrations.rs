@@ -0,0 +1,248 @@
+use sqlx::{Error, PgPool};
+use tracing::info;
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+/// Ordered, numbered schema changes. Each pair of `.up.sql`/`.down.sql` files
+/// lives under `migrations/` and is embedded into the binary at compile time,
+/// so the list here is the single source of truth for which migrations exist
+/// and in what order; the `_migrations` table records which have been applied.
+static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "extensions",
+        up: include_str!("../migrations/0001_extensions.up.sql"),
+        down: include_str!("../migrations/0001_extensions.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "updated_at_function",
+        up: include_str!("../migrations/0002_updated_at_function.up.sql"),
+        down: include_str!("../migrations/0002_updated_at_function.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "snapshots",
+        up: include_str!("../migrations/0003_snapshots.up.sql"),
+        down: include_str!("../migrations/0003_snapshots.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "urls",
+        up: include_str!("../migrations/0004_urls.up.sql"),
+        down: include_str!("../migrations/0004_urls.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "texts",
+        up: include_str!("../migrations/0005_texts.up.sql"),
+        down: include_str!("../migrations/0005_texts.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "embeddings",
+        up: include_str!("../migrations/0006_embeddings.up.sql"),
+        down: include_str!("../migrations/0006_embeddings.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "job_queue",
+        up: include_str!("../migrations/0007_job_queue.up.sql"),
+        down: include_str!("../migrations/0007_job_queue.down.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "job_queue_attempts",
+        up: include_str!("../migrations/0008_job_queue_attempts.up.sql"),
+        down: include_str!("../migrations/0008_job_queue_attempts.down.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "texts_unique_snapshot",
+        up: include_str!("../migrations/0009_texts_unique_snapshot.up.sql"),
+        down: include_str!("../migrations/0009_texts_unique_snapshot.down.sql"),
+    },
+];
+
+async fn create_migrations_table(pool: &PgPool) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS _migrations (
+          version BIGINT PRIMARY KEY,
+          name TEXT NOT NULL,
+          applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Splits a migration file into individual statements on top-level `;`,
+/// treating anything between a pair of `$$` markers as opaque so a
+/// plpgsql function body's internal semicolons don't get split apart.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut in_dollar_quote = false;
+    let bytes = sql.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'$' && i + 1 < bytes.len() && bytes[i + 1] == b'$' {
+            in_dollar_quote = !in_dollar_quote;
+            i += 2;
+            continue;
+        }
+
+        if bytes[i] == b';' && !in_dollar_quote {
+            let statement = sql[start..i].trim();
+            if !statement.is_empty() {
+                statements.push(statement);
+            }
+            start = i + 1;
+        }
+
+        i += 1;
+    }
+
+    let statement = sql[start..].trim();
+    if !statement.is_empty() {
+        statements.push(statement);
+    }
+
+    statements
+}
+
+/// Applies every migration in `MIGRATIONS` that isn't already recorded in
+/// `_migrations`, in version order, each inside its own transaction.
+pub async fn run(pool: &PgPool) -> Result<(), Error> {
+    create_migrations_table(pool).await?;
+
+    let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _migrations")
+        .fetch_all(pool)
+        .await?;
+
+    for migration in MIGRATIONS {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+
+        info!("Applying migration {:04}_{}", migration.version, migration.name);
+
+        let mut tx = pool.begin().await?;
+
+        for statement in split_statements(migration.up) {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        sqlx::query("INSERT INTO _migrations (version, name) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Rolls the schema back to (but not including) `target_version`, running
+/// down migrations for every applied version above it, newest first.
+pub async fn rollback_to(pool: &PgPool, target_version: i64) -> Result<(), Error> {
+    create_migrations_table(pool).await?;
+
+    let mut applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM _migrations")
+        .fetch_all(pool)
+        .await?;
+    applied.sort_unstable_by(|a, b| b.cmp(a));
+
+    for version in applied {
+        if version <= target_version {
+            break;
+        }
+
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.version == version)
+            .expect("version recorded in _migrations has no matching migration definition");
+
+        info!(
+            "Rolling back migration {:04}_{}",
+            migration.version, migration.name
+        );
+
+        let mut tx = pool.begin().await?;
+
+        for statement in split_statements(migration.down) {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        sqlx::query("DELETE FROM _migrations WHERE version = $1")
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_top_level_semicolons() {
+        let sql = "CREATE TABLE a (id int);\nCREATE TABLE b (id int);";
+
+        assert_eq!(
+            split_statements(sql),
+            vec!["CREATE TABLE a (id int)", "CREATE TABLE b (id int)"]
+        );
+    }
+
+    #[test]
+    fn ignores_semicolons_inside_a_dollar_quoted_function_body() {
+        let sql = "CREATE FUNCTION f() RETURNS trigger AS $$\n\
+                   BEGIN\n\
+                     NEW.updated_at = NOW();\n\
+                     RETURN NEW;\n\
+                   END;\n\
+                   $$ LANGUAGE plpgsql;";
+
+        let statements = split_statements(sql);
+
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains("NEW.updated_at = NOW();"));
+    }
+
+    #[test]
+    fn handles_a_dollar_quoted_statement_followed_by_another() {
+        let sql = "CREATE FUNCTION f() RETURNS trigger AS $$\n\
+                   BEGIN RETURN NEW; END;\n\
+                   $$ LANGUAGE plpgsql;\n\
+                   CREATE TRIGGER t BEFORE UPDATE ON a EXECUTE FUNCTION f();";
+
+        let statements = split_statements(sql);
+
+        assert_eq!(statements.len(), 2);
+        assert!(statements[1].starts_with("CREATE TRIGGER"));
+    }
+
+    #[test]
+    fn ignores_blank_statements() {
+        let sql = "CREATE TABLE a (id int);;;\n\n";
+
+        assert_eq!(split_statements(sql), vec!["CREATE TABLE a (id int)"]);
+    }
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Error, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::error;
+use uuid::Uuid;
+
+/// A job is retried this many times (including the first attempt) before it
+/// is parked in `'failed'` instead of being handed back to the `'new'` queue,
+/// so a poison job (bad input, a permanently failing API key) can't spin
+/// forever.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "job_status", rename_all = "lowercase")]
+pub enum JobStatus {
+    New,
+    Running,
+    Failed,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct Job {
+    pub id: Uuid,
+    pub queue: String,
+    pub job: serde_json::Value,
+    pub status: JobStatus,
+    pub message: Option<String>,
+    pub heartbeat: Option<chrono::DateTime<chrono::Utc>>,
+    pub attempts: i32,
+}
+
+pub async fn enqueue(pool: &PgPool, queue: &str, job: serde_json::Value) -> Result<Uuid, Error> {
+    let (id,): (Uuid,) = sqlx::query_as(
+        r#"
+        INSERT INTO job_queue (queue, job, status)
+        VALUES ($1, $2, 'new')
+        RETURNING id
+        "#,
+    )
+    .bind(queue)
+    .bind(job)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id)
+}
+
+pub async fn dequeue(pool: &PgPool, queue: &str) -> Result<Option<Job>, Error> {
+    let mut tx = pool.begin().await?;
+
+    let job = sqlx::query_as::<_, Job>(
+        r#"
+        SELECT id, queue, job, status, message, heartbeat, attempts
+        FROM job_queue
+        WHERE queue = $1 AND status = 'new'
+        ORDER BY created_at
+        FOR UPDATE SKIP LOCKED
+        LIMIT 1
+        "#,
+    )
+    .bind(queue)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(job) = &job {
+        sqlx::query(
+            r#"
+            UPDATE job_queue
+            SET status = 'running', heartbeat = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(job.id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(job)
+}
+
+pub async fn heartbeat(pool: &PgPool, id: Uuid) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE job_queue
+        SET heartbeat = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Bumps `heartbeat` on an interval until the returned handle is aborted;
+/// dropping the handle does not stop the task. Workers abort it once the
+/// job finishes: a task left running keeps the row's heartbeat fresh, so
+/// the reaper would never treat that row as stale, even after it has been
+/// requeued and picked up by a worker that then crashed.
+pub fn spawn_heartbeat(pool: Arc<PgPool>, id: Uuid, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await; // first tick fires immediately; the dequeue already set heartbeat
+        loop {
+            ticker.tick().await;
+            if let Err(e) = heartbeat(&pool, id).await {
+                error!("Failed to update heartbeat for job {}: {}", id, e);
+            }
+        }
+    })
+}
+
+pub async fn complete(pool: &PgPool, id: Uuid) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM job_queue
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Requeues a failed job for another attempt, unless it has now exhausted
+/// `MAX_ATTEMPTS`, in which case it is parked in the terminal `'failed'`
+/// status so a poison job stops being re-dequeued every few seconds.
+pub async fn fail(pool: &PgPool, id: Uuid, message: &str) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        UPDATE job_queue
+        SET attempts = attempts + 1,
+            message = $2,
+            status = CASE WHEN attempts + 1 >= $3 THEN 'failed'::job_status ELSE 'new'::job_status END
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(message)
+    .bind(MAX_ATTEMPTS)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Resets jobs stuck in `running` whose heartbeat is older than `timeout`,
+/// so a crashed worker's job gets picked up again instead of stalling
+/// forever. Counts as an attempt, so a job that keeps crashing a worker
+/// eventually lands in `'failed'` instead of being reaped indefinitely.
+pub async fn reap_stale_jobs(pool: &PgPool, timeout: Duration) -> Result<u64, Error> {
+    let timeout_secs = timeout.as_secs_f64();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE job_queue
+        SET attempts = attempts + 1,
+            message = 'reaped: heartbeat timeout',
+            status = CASE WHEN attempts + 1 >= $2 THEN 'failed'::job_status ELSE 'new'::job_status END
+        WHERE status = 'running'
+          AND heartbeat < NOW() - make_interval(secs => $1)
+        "#,
+    )
+    .bind(timeout_secs)
+    .bind(MAX_ATTEMPTS)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn reaper(pool: std::sync::Arc<PgPool>, interval: Duration, timeout: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match reap_stale_jobs(&pool, timeout).await {
+            Ok(0) => (),
+            Ok(n) => tracing::info!("Reaped {} stale job(s)", n),
+            Err(e) => error!("Failed to reap stale jobs: {}", e),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use axum::{
     response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{Error, PgPool};
+use sqlx::PgPool;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 use uuid::Uuid;
@@ -33,134 +33,26 @@ struct ResponseBody {
     data: Option<String>,
 }
 
-pub async fn create_snapshots_table(pool: &PgPool) -> Result<(), Error> {
-    // CREATE EXTENSION and TABLE commands are separated.
-    match sqlx::query(
-        r#"
-        CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create extension: {}", e);
-        }
-    }
-
-    match sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS snapshots (
-          id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
-          url TEXT NOT NULL,
-          content JSONB,
-          created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-          updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create table: {}", e);
-        }
-    }
-
-    // Each part of function and trigger creation is a separate command.
-    match sqlx::query(
-        r#"
-        CREATE OR REPLACE FUNCTION update_updated_at_column()
-          RETURNS TRIGGER AS $$
-          BEGIN
-            NEW.updated_at = NOW();
-            RETURN NEW;
-          END;
-          $$ language 'plpgsql';
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create function: {}", e);
-        }
-    }
-
-    match sqlx::query(
-        r#"
-        DROP TRIGGER IF EXISTS update_snapshots_updated_at ON snapshots;
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to drop trigger: {}", e);
-        }
-    }
-
-    match sqlx::query(
-        r#"
-        CREATE TRIGGER update_snapshots_updated_at
-          BEFORE UPDATE ON snapshots
-          FOR EACH ROW
-          EXECUTE FUNCTION update_updated_at_column();
-        "#,
-    )
-    .execute(pool)
-    .await
-    {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create trigger: {}", e);
-        }
-    }
-
-    Ok(())
-}
-
 pub async fn insert_snapshot(
     Extension(pool): Extension<Arc<PgPool>>,
     Json(payload): Json<Payload>,
-) -> impl IntoResponse {
-    let url = match payload.url {
-        Some(url) => url,
-        None => return (StatusCode::BAD_REQUEST, "Missing url"),
-    };
-    let html = match payload.html {
-        Some(html) => html,
-        None => return (StatusCode::BAD_REQUEST, "Missing html"),
-    };
-
-    if !crate::validate_url(&url).unwrap() {
-        return (StatusCode::BAD_REQUEST, "only absolute urls are allowed");
+) -> Result<impl IntoResponse, crate::Error> {
+    let url = payload
+        .url
+        .ok_or_else(|| crate::Error::BadRequest("missing url".to_string()))?;
+    let html = payload
+        .html
+        .ok_or_else(|| crate::Error::BadRequest("missing html".to_string()))?;
+
+    if !crate::validate_url(&url).map_err(|_| crate::Error::InvalidUrl(url.clone()))? {
+        return Err(crate::Error::InvalidUrl(url));
     }
 
     let pool = pool.as_ref();
 
-    match crate::urls::crawl_url(pool, &url).await {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to crawl url: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to crawl url");
-        }
-    };
+    crate::urls::crawl_url(pool, &url).await?;
 
-    let last_snapshot = match fetch_last_snapshot(&url, pool).await {
-        Ok(last_snapshot) => last_snapshot,
-        Err(e) => {
-            error!("Failed to fetch last snapshot: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to fetch last snapshot",
-            );
-        }
-    };
+    let last_snapshot = fetch_last_snapshot(&url, pool).await?;
 
     match &last_snapshot {
         Some(last_snapshot) => {
@@ -174,7 +66,7 @@ pub async fn insert_snapshot(
     if let Some(last_snapshot) = last_snapshot {
         if last_snapshot.html == Some(html.to_string()) {
             info!("No need to insert the same content twice.");
-            return (StatusCode::OK, "No need to insert the same content twice.");
+            return Ok((StatusCode::OK, "No need to insert the same content twice."));
         }
     }
 
@@ -183,9 +75,9 @@ pub async fn insert_snapshot(
         html: Some(html.to_string()),
     };
 
-    let content_value = serde_json::to_value(&content).unwrap();
+    let content_value = serde_json::to_value(&content)?;
 
-    let record = match sqlx::query_as::<_, Snapshot>(
+    let record = sqlx::query_as::<_, Snapshot>(
         r#"
         INSERT INTO snapshots (url, content)
         VALUES ($1, $2)
@@ -194,32 +86,28 @@ pub async fn insert_snapshot(
     )
     .bind(&url)
     .bind(content_value)
-    .fetch_optional(pool)
+    .fetch_one(pool)
+    .await?;
+
+    add_relation_to_url(&url, &record.id, pool).await?;
+
+    if let Err(e) = crate::queue::enqueue(
+        pool,
+        "parse",
+        serde_json::json!({ "snapshot_id": record.id }),
+    )
     .await
     {
-        Ok(record) => record,
-        Err(e) => {
-            error!("Failed to insert snapshot: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to insert snapshot",
-            );
-        }
-    };
-
-    match record {
-        Some(record) => {
-            add_relation_to_url(&url, &record.id, pool).await.unwrap();
-            (StatusCode::OK, "Snapshot inserted")
-        }
-        None => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to insert snapshot",
-        ),
+        error!(
+            "Failed to enqueue parse job for snapshot {}: {}",
+            record.id, e
+        );
     }
+
+    Ok((StatusCode::OK, "Snapshot inserted"))
 }
 
-async fn fetch_last_snapshot(url: &str, pool: &PgPool) -> Result<Option<Content>, Error> {
+async fn fetch_last_snapshot(url: &str, pool: &PgPool) -> Result<Option<Content>, crate::Error> {
     debug!("Fetching last snapshot for {}", url);
 
     let sql = r#"
@@ -229,16 +117,10 @@ async fn fetch_last_snapshot(url: &str, pool: &PgPool) -> Result<Option<Content>
         LIMIT 1
         "#;
 
-    let result = match sqlx::query_as::<_, Snapshot>(sql)
+    let result = sqlx::query_as::<_, Snapshot>(sql)
         .bind(url)
         .fetch_optional(pool)
-        .await
-    {
-        Ok(result) => result,
-        Err(e) => {
-            return Err(e);
-        }
-    };
+        .await?;
 
     let content = match result {
         Some(result) => result.content,
@@ -246,18 +128,18 @@ async fn fetch_last_snapshot(url: &str, pool: &PgPool) -> Result<Option<Content>
     };
 
     if let Some(content) = content {
-        let content: Content = serde_json::from_value(content).unwrap();
+        let content: Content = serde_json::from_value(content)?;
         Ok(Some(content))
     } else {
         Ok(None)
     }
 }
 
-async fn add_relation_to_url(url: &str, snapshot_id: &Uuid, pool: &PgPool) -> Result<(), Error> {
-    //let snapshot_uuid = Uuid::parse_str(snapshot_id).map_err(|e| sqlx::Error::TypeNotFound {
-    //    type_name: snapshot_id.to_string(),
-    //})?;
-
+async fn add_relation_to_url(
+    url: &str,
+    snapshot_id: &Uuid,
+    pool: &PgPool,
+) -> Result<(), crate::Error> {
     sqlx::query(
         r#"
         UPDATE urls
@@ -273,7 +155,9 @@ async fn add_relation_to_url(url: &str, snapshot_id: &Uuid, pool: &PgPool) -> Re
     Ok(())
 }
 
-pub async fn fetch_url_to_snapshot(Extension(pool): Extension<Arc<PgPool>>) -> impl IntoResponse {
+pub async fn fetch_url_to_snapshot(
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Result<impl IntoResponse, crate::Error> {
     let pool = pool.as_ref();
 
     let sql = r#"
@@ -283,28 +167,13 @@ pub async fn fetch_url_to_snapshot(Extension(pool): Extension<Arc<PgPool>>) -> i
         LIMIT 1
         "#;
 
-    let result = match sqlx::query_as::<_, crate::urls::Record>(sql)
+    if let Some(record) = sqlx::query_as::<_, crate::urls::Record>(sql)
         .fetch_optional(pool)
-        .await
+        .await?
     {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Failed to fetch url: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseBody { data: None }),
-            );
-        }
-    };
-
-    if let Some(record) = result {
-        let url = record.url();
-        return (
-            StatusCode::OK,
-            Json(ResponseBody {
-                data: Some(url.to_string()),
-            }),
-        );
+        return Ok(Json(ResponseBody {
+            data: Some(record.url().to_string()),
+        }));
     }
 
     let sql = r#"
@@ -313,33 +182,12 @@ pub async fn fetch_url_to_snapshot(Extension(pool): Extension<Arc<PgPool>>) -> i
         LIMIT 1
         "#;
 
-    let result = match sqlx::query_as::<_, crate::urls::Record>(sql)
+    let record = sqlx::query_as::<_, crate::urls::Record>(sql)
         .fetch_optional(pool)
-        .await
-    {
-        Ok(result) => result,
-        Err(e) => {
-            error!("Failed to fetch url: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ResponseBody { data: None }),
-            );
-        }
-    };
+        .await?
+        .ok_or(crate::Error::NotFound)?;
 
-    match result {
-        Some(record) => {
-            let url = record.url();
-            (
-                StatusCode::OK,
-                Json(ResponseBody {
-                    data: Some(url.to_string()),
-                }),
-            )
-        }
-        None => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ResponseBody { data: None }),
-        ),
-    }
+    Ok(Json(ResponseBody {
+        data: Some(record.url().to_string()),
+    }))
 }
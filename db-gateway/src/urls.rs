@@ -6,7 +6,6 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use sqlx::{Error, PgPool};
 use std::sync::Arc;
-use tracing::error;
 use uuid::Uuid;
 
 #[derive(Deserialize)]
@@ -46,109 +45,34 @@ impl Record {
     }
 }
 
-pub async fn create_urls_table(pool: &PgPool) -> Result<(), Error> {
-    // CREATE EXTENSION and TABLE commands are separated.
-    sqlx::query(
-        r#"
-        CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS urls (
-          id uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
-          url TEXT NOT NULL UNIQUE,
-          content JSONB,
-          snapshot_id uuid,
-          FOREIGN KEY (snapshot_id) REFERENCES snapshots(id),
-          created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-          updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Each part of function and trigger creation is a separate command.
-    sqlx::query(
-        r#"
-        CREATE OR REPLACE FUNCTION update_updated_at_column()
-          RETURNS TRIGGER AS $$
-          BEGIN
-            NEW.updated_at = NOW();
-            RETURN NEW;
-          END;
-          $$ language 'plpgsql';
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        DROP TRIGGER IF EXISTS update_urls_updated_at ON urls;
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TRIGGER update_urls_updated_at
-          BEFORE UPDATE ON urls
-          FOR EACH ROW
-          EXECUTE FUNCTION update_updated_at_column();
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    Ok(())
-}
-
 pub async fn insert_url(
     Extension(pool): Extension<Arc<PgPool>>,
     Json(payload): Json<Payload>,
-) -> impl IntoResponse {
-    let url = match payload.url {
-        Some(url) => url,
-        None => return (StatusCode::BAD_REQUEST, "Missing url"),
-    };
-
-    if !crate::validate_url(&url).unwrap() {
-        return (StatusCode::BAD_REQUEST, "only absolute urls are allowed");
+) -> Result<impl IntoResponse, crate::Error> {
+    let url = payload
+        .url
+        .ok_or_else(|| crate::Error::BadRequest("missing url".to_string()))?;
+
+    if !crate::validate_url(&url).map_err(|_| crate::Error::InvalidUrl(url.clone()))? {
+        return Err(crate::Error::InvalidUrl(url));
     }
 
     let content = Content::from_url(&url);
 
     let pool = pool.as_ref();
 
-    match sqlx::query(
+    sqlx::query(
         r#"
         INSERT INTO urls (url, content)
-        VALUES ($1, $2) ON CONFLICT (url) DO NOTHING
+        VALUES ($1, $2)
         "#,
     )
     .bind(url)
-    .bind(serde_json::to_value(content).unwrap())
+    .bind(serde_json::to_value(content)?)
     .execute(pool)
-    .await
-    {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                (StatusCode::OK, "URL already exists")
-            } else {
-                (StatusCode::OK, "Url inserted")
-            }
-        }
-        Err(e) => {
-            error!("Failed to insert url: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to insert url")
-        }
-    }
+    .await?;
+
+    Ok((StatusCode::OK, "Url inserted"))
 }
 
 pub async fn crawl_url(pool: &PgPool, url: &str) -> Result<(), Error> {
@@ -174,14 +98,13 @@ pub async fn crawl_url(pool: &PgPool, url: &str) -> Result<(), Error> {
 pub async fn delete_url(
     Extension(pool): Extension<Arc<PgPool>>,
     Json(payload): Json<Payload>,
-) -> impl IntoResponse {
-    let url = match payload.url {
-        Some(url) => url,
-        None => return (StatusCode::BAD_REQUEST, "Missing url"),
-    };
+) -> Result<impl IntoResponse, crate::Error> {
+    let url = payload
+        .url
+        .ok_or_else(|| crate::Error::BadRequest("missing url".to_string()))?;
     let pool = pool.as_ref();
 
-    match sqlx::query(
+    sqlx::query(
         r#"
         DELETE FROM urls
         WHERE url = $1
@@ -189,12 +112,7 @@ pub async fn delete_url(
     )
     .bind(url)
     .execute(pool)
-    .await
-    {
-        Ok(_) => (StatusCode::OK, "Url deleted"),
-        Err(e) => {
-            error!("Failed to delete url: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to delete url")
-        }
-    }
+    .await?;
+
+    Ok((StatusCode::OK, "Url deleted"))
 }